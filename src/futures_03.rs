@@ -46,12 +46,25 @@
 //! ```
 //! By using `futurify` you'll be able to run the closure in a new thread and get the returned value in a future.
 
-use futures03::task::Poll;
-use futures03::Future;
+use crate::pool;
+use futures03::task::{Poll, Waker};
+use futures03::{Future, Stream};
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::pin::Pin;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
 use std::task::Context;
-use std::thread;
+
+/// Items are buffered in the stream's internal channel up to this many
+/// entries before the producing thread parks, giving the blocking source
+/// natural backpressure.
+const STREAM_CHANNEL_BOUND: usize = 16;
+
+/// The error carried by a [`Futurified`] created with [`wrap_catch`] or
+/// [`wrap_eager_catch`] when the wrapped closure panics.
+pub type PanicPayload = Box<dyn Any + Send + 'static>;
 
 /// Future wrapping a sync function that will be executed
 /// in a separate thread.
@@ -62,6 +75,8 @@ pub struct Futurified<T: Send + 'static, F: FnOnce() -> T + Send + Unpin> {
     rx: Receiver<T>,
     wrapped: Option<F>,
     is_running: bool,
+    waker: Arc<Mutex<Option<Waker>>>,
+    cancelled: Arc<AtomicBool>,
 }
 /// Wraps a closure to be executed in a separate thread.
 /// It will be executed once the returning Future is polled.
@@ -76,6 +91,8 @@ pub fn wrap<T: Send + 'static, F: FnOnce() -> T + Send + 'static + Unpin>(
         rx,
         wrapped: Some(wrapped),
         is_running: false,
+        waker: Arc::new(Mutex::new(None)),
+        cancelled: Arc::new(AtomicBool::new(false)),
     }
 }
 
@@ -91,28 +108,89 @@ pub fn wrap_eager<T: Send + 'static, F: FnOnce() -> T + Send + 'static + Unpin>(
     this
 }
 
+/// Like `wrap`, but catches a panic in the closure instead of leaving the
+/// returned future pending forever.
+///
+/// The future resolves to `Ok(value)` on success or `Err(payload)` if the
+/// closure panicked; the panic's default console output is suppressed for
+/// the worker thread running it.
+pub fn wrap_catch<T: Send + 'static, F: FnOnce() -> T + Send + 'static + Unpin>(
+    wrapped: F,
+) -> Futurified<Result<T, PanicPayload>, impl FnOnce() -> Result<T, PanicPayload> + Send + Unpin> {
+    wrap(move || pool::suppress_panic_output(|| catch_unwind(AssertUnwindSafe(wrapped))))
+}
+
+/// Similar to `wrap_catch` but this will execute the closure even if the
+/// future is never polled.
+///
+/// See [`wrap_catch`] for more details.
+pub fn wrap_eager_catch<T: Send + 'static, F: FnOnce() -> T + Send + 'static + Unpin>(
+    wrapped: F,
+) -> Futurified<Result<T, PanicPayload>, impl FnOnce() -> Result<T, PanicPayload> + Send + Unpin> {
+    let mut this = wrap_catch(wrapped);
+    this.run();
+    this
+}
+
 impl<T: Send + 'static, F: FnOnce() -> T + Send + 'static + Unpin> Futurified<T, F> {
     fn run(&mut self) {
         self.is_running = true;
         let tx = self.tx.clone();
         let sfn = self.wrapped.take().unwrap();
-        thread::spawn(move || {
+        let waker = self.waker.clone();
+        let cancelled = self.cancelled.clone();
+        pool::submit(Box::new(move || {
+            if cancelled.load(Ordering::SeqCst) {
+                return;
+            }
             let result = sfn();
-            if let Err(e) = tx.send(result) {
-                println!("Error sending result: {}", e)
+            // The receiver is gone once the handle has been `cancel`led or
+            // `detach`ed and the task has already completed; that's the
+            // documented, expected shape of those paths, not an error to
+            // report.
+            let _ = tx.send(result);
+            if let Some(waker) = waker.lock().unwrap().take() {
+                waker.wake();
             }
-        });
+        }));
+    }
+
+    /// Cancels this task, returning its result if it had already completed
+    /// or `None` if it was still running (or never started).
+    ///
+    /// Cancellation is cooperative: the worker thread is only stopped from
+    /// starting the closure if it hasn't done so yet, since OS threads can't
+    /// be forcibly interrupted mid-execution. Either way the handle is
+    /// dropped and its result, if any arrives later, is discarded.
+    pub fn cancel(self) -> Option<T> {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.rx.try_recv().ok()
+    }
+
+    /// Lets the wrapped closure run to completion in the background without
+    /// keeping this handle around to await its result.
+    pub fn detach(mut self) {
+        if !self.is_running {
+            self.run();
+        }
     }
 }
 
 impl<T: Send + 'static, F: FnOnce() -> T + Send + 'static + Unpin> Future for Futurified<T, F> {
     type Output = T;
 
-    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let mut_self = self.get_mut();
         if !mut_self.is_running {
             mut_self.run();
         }
+        // Register the waker before checking the channel: if we checked
+        // first, the worker could finish and see no waker to wake in the
+        // gap between our `try_recv` and storing one, leaving us parked
+        // forever. Registering first means any completion that happens
+        // after is guaranteed to observe (and fire) this waker, so we
+        // re-check the channel afterwards in case it already arrived.
+        *mut_self.waker.lock().unwrap() = Some(cx.waker().clone());
         if let Ok(x) = mut_self.rx.try_recv() {
             Poll::Ready(x)
         } else {
@@ -120,3 +198,64 @@ impl<T: Send + 'static, F: FnOnce() -> T + Send + 'static + Unpin> Future for Fu
         }
     }
 }
+
+/// Stream wrapping a blocking, iterator-producing closure that is run on a
+/// separate thread.
+///
+/// Items are fed back through a bounded channel: the worker thread parks
+/// whenever the channel is full, giving the blocking source natural
+/// backpressure. The stream ends once the iterator is exhausted.
+pub struct FuturifiedStream<T: Send + 'static> {
+    rx: Receiver<T>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+/// Wraps a closure producing a blocking iterator, running it on a separate
+/// thread and yielding its items as an async `Stream`.
+///
+/// Unlike `wrap`, the iterator starts being drained immediately so it can
+/// begin filling the bounded channel ahead of the first poll.
+pub fn wrap_stream<I, T, F>(f: F) -> FuturifiedStream<T>
+where
+    T: Send + 'static,
+    I: Iterator<Item = T> + Send + 'static,
+    F: FnOnce() -> I + Send + 'static,
+{
+    let (tx, rx) = sync_channel(STREAM_CHANNEL_BOUND);
+    let waker: Arc<Mutex<Option<Waker>>> = Arc::new(Mutex::new(None));
+    let producer_waker = waker.clone();
+    pool::submit(Box::new(move || {
+        for item in f() {
+            if tx.send(item).is_err() {
+                break;
+            }
+            if let Some(waker) = producer_waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        }
+        // Disconnect before the final wake so the poll it triggers observes
+        // the end of the stream instead of an empty but still open channel.
+        drop(tx);
+        if let Some(waker) = producer_waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }));
+    FuturifiedStream { rx, waker }
+}
+
+impl<T: Send + 'static> Stream for FuturifiedStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut_self = self.get_mut();
+        // Register before checking, for the same reason as `Futurified::poll`:
+        // otherwise a producer that sends its last item and exits between our
+        // check and our registration is never seen again.
+        *mut_self.waker.lock().unwrap() = Some(cx.waker().clone());
+        match mut_self.rx.try_recv() {
+            Ok(item) => Poll::Ready(Some(item)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+}
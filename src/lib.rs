@@ -5,6 +5,9 @@
 //!
 //! By using `futurify` you'll be able to run the closure in a new thread and get the returned value in a future.
 
+mod pool;
+pub use pool::configure_pool;
+
 #[cfg(feature = "futures_01")]
 mod futures_01;
 #[cfg(feature = "futures_01")]
@@ -18,6 +21,16 @@ mod futures_03;
 pub use futures_03::wrap;
 #[cfg(feature = "futures_03")]
 pub use futures_03::wrap_eager;
+#[cfg(feature = "futures_03")]
+pub use futures_03::wrap_catch;
+#[cfg(feature = "futures_03")]
+pub use futures_03::wrap_eager_catch;
+#[cfg(feature = "futures_03")]
+pub use futures_03::PanicPayload;
+#[cfg(feature = "futures_03")]
+pub use futures_03::wrap_stream;
+#[cfg(feature = "futures_03")]
+pub use futures_03::FuturifiedStream;
 
 //#[cfg(not(feature = "futures_01"))]
 //mod futures_03;
@@ -0,0 +1,140 @@
+//! A small, lazily-initialized blocking thread pool.
+//!
+//! `wrap`/`wrap_eager` offload work onto this pool instead of spawning a
+//! fresh OS thread per call, so bursty workloads don't pay thread-per-task
+//! overhead. Threads are created on demand, up to a configurable maximum,
+//! and idle threads exit after sitting unused for `idle_timeout`.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+const DEFAULT_MAX_THREADS: usize = 512;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<Job>,
+    live_threads: usize,
+    idle_threads: usize,
+}
+
+struct Pool {
+    state: Mutex<State>,
+    condvar: Condvar,
+    max_threads: usize,
+    idle_timeout: Duration,
+}
+
+impl Pool {
+    fn new(max_threads: usize, idle_timeout: Duration) -> Self {
+        Pool {
+            state: Mutex::new(State::default()),
+            condvar: Condvar::new(),
+            max_threads,
+            idle_timeout,
+        }
+    }
+
+    /// Enqueues `job`, waking an idle worker if one is parked, spawning a
+    /// new one if the pool hasn't yet reached `max_threads`, or otherwise
+    /// leaving it queued for whichever worker asks for more work next.
+    fn submit(&'static self, job: Job) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.push_back(job);
+        if state.idle_threads > 0 {
+            drop(state);
+            self.condvar.notify_one();
+        } else if state.live_threads < self.max_threads {
+            state.live_threads += 1;
+            drop(state);
+            self.spawn_worker();
+        }
+    }
+
+    fn spawn_worker(&'static self) {
+        thread::spawn(move || loop {
+            let mut state = self.state.lock().unwrap();
+            let job = loop {
+                if let Some(job) = state.queue.pop_front() {
+                    break Some(job);
+                }
+                state.idle_threads += 1;
+                let (guard, timeout) =
+                    self.condvar.wait_timeout(state, self.idle_timeout).unwrap();
+                state = guard;
+                state.idle_threads -= 1;
+                if timeout.timed_out() {
+                    // Re-check under the same lock we're about to drop
+                    // `live_threads` under, so a concurrent `submit` can
+                    // never enqueue a job just as we decide to exit and
+                    // have it orphaned until the next submission.
+                    break state.queue.pop_front();
+                }
+            };
+            match job {
+                Some(job) => {
+                    drop(state);
+                    job();
+                }
+                None => {
+                    state.live_threads -= 1;
+                    break;
+                }
+            }
+        });
+    }
+}
+
+static CONFIG: OnceLock<(usize, Duration)> = OnceLock::new();
+static POOL: OnceLock<Pool> = OnceLock::new();
+
+/// Tunes the shared blocking-thread pool used by `wrap`/`wrap_eager`.
+///
+/// Must be called before the first closure is wrapped; once the pool has
+/// been lazily created with its defaults (or a previous call to this
+/// function), later calls have no effect.
+pub fn configure_pool(max_threads: usize, idle_timeout: Duration) {
+    let _ = CONFIG.set((max_threads, idle_timeout));
+}
+
+pub(crate) fn submit(job: Job) {
+    let pool = POOL.get_or_init(|| {
+        let (max_threads, idle_timeout) =
+            *CONFIG.get_or_init(|| (DEFAULT_MAX_THREADS, DEFAULT_IDLE_TIMEOUT));
+        Pool::new(max_threads, idle_timeout)
+    });
+    pool.submit(job);
+}
+
+thread_local! {
+    static SUPPRESS_PANIC_OUTPUT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f`, suppressing the default panic hook's printing for any panic
+/// that happens while it runs on the *current* thread.
+///
+/// This installs a process-wide hook (once) that defers to the original
+/// hook unless the panicking thread has opted in via a thread-local flag,
+/// so it's safe to call from multiple pool threads running unrelated jobs
+/// concurrently.
+pub(crate) fn suppress_panic_output<R>(f: impl FnOnce() -> R) -> R {
+    static HOOK_INSTALLED: OnceLock<()> = OnceLock::new();
+    HOOK_INSTALLED.get_or_init(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let suppressed = SUPPRESS_PANIC_OUTPUT.with(Cell::get);
+            if !suppressed {
+                default_hook(info);
+            }
+        }));
+    });
+    SUPPRESS_PANIC_OUTPUT.with(|s| s.set(true));
+    let result = f();
+    SUPPRESS_PANIC_OUTPUT.with(|s| s.set(false));
+    result
+}